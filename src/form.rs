@@ -0,0 +1,66 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt::Debug;
+
+use crate::MetaType;
+
+/// Controls how a type identifier graph represents its indirections.
+///
+/// Implementors decide what a `TypeId<F>` actually stores in place of a
+/// nested type or a string: either the value itself, pre-compaction, or a
+/// lightweight registry index, post-compaction.
+///
+/// # Note
+///
+/// The bounds on the associated types (rather than on `F` alone) are what
+/// let `TypeId<F>` and friends derive `PartialEq`/`Eq`/`PartialOrd`/`Ord` —
+/// the derived impls call through to these associated types, and a plain
+/// `#[derive(...)]` only ever adds a bound on `F` itself, never on types
+/// reached through it.
+pub trait Form: Clone + PartialEq + Eq + PartialOrd + Ord + Debug {
+	/// The type used to represent a type identifier directly.
+	type TypeId: Clone + PartialEq + Eq + PartialOrd + Ord + Debug;
+	/// The type used to represent a type identifier held by indirection,
+	/// e.g. the element type of a slice or array.
+	type IndirectTypeId: Clone + PartialEq + Eq + PartialOrd + Ord + Debug;
+	/// The type used to represent strings, e.g. type and namespace names.
+	type String: Clone + PartialEq + Eq + PartialOrd + Ord + Debug;
+}
+
+/// A type identifier graph expressed in terms of compile-time meta types.
+///
+/// This is the form produced directly by [`HasTypeId`](crate::HasTypeId)
+/// implementations, before a [`Registry`](crate::Registry) compacts it.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Default, Debug)]
+pub struct MetaForm;
+
+impl Form for MetaForm {
+	type TypeId = MetaType;
+	type IndirectTypeId = MetaType;
+	type String = &'static str;
+}
+
+/// A type identifier graph in which every type and string is a lightweight
+/// index into a [`Registry`](crate::Registry)'s tables.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Default, Debug)]
+pub struct CompactForm;
+
+impl Form for CompactForm {
+	type TypeId = u32;
+	type IndirectTypeId = u32;
+	type String = u32;
+}
@@ -0,0 +1,55 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metadata for type registration and type identification.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod form;
+mod meta_type;
+mod registry;
+mod tm_std;
+mod type_id;
+mod utils;
+
+pub use self::{
+	form::{CompactForm, Form, MetaForm},
+	meta_type::MetaType,
+	registry::{IntoCompact, Registry},
+	type_id::{
+		FromTablesError, HasTypeId, Namespace, NamespaceError, TypeId, TypeIdArray, TypeIdCustom, TypeIdFnPtr, TypeIdPointer,
+		TypeIdPrimitive, TypeIdReference, TypeIdSlice, TypeIdTuple, TypeMatcher, TypeSubstitutes,
+	},
+};
+
+/// Implementors provide the static [`TypeId`] that describes their own shape.
+///
+/// This is the trait a `#[derive(Metadata)]` macro (outside this crate)
+/// implements for user types; anything implementing [`HasTypeId`]
+/// automatically satisfies it too.
+pub trait Metadata {
+	/// Returns the static type identifier for `Self`.
+	fn type_id() -> TypeId;
+}
+
+impl<T> Metadata for T
+where
+	T: HasTypeId,
+{
+	fn type_id() -> TypeId {
+		<T as HasTypeId>::type_id()
+	}
+}
@@ -0,0 +1,386 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tm_std::*;
+
+use core::any::TypeId as AnyTypeId;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::{IntoCompact, Metadata, Namespace, Registry, TypeId};
+
+/// Describes how a Rust type should be registered with a `Registry`.
+///
+/// A naively monomorphized registration of `Option<u8>`, `Option<u32>` and
+/// `Option<bool>` would duplicate the definition of `Option` three times
+/// over. Instead, the generic definition of a type is registered once,
+/// keyed on the `any::TypeId` of a marker that identifies the generic
+/// family, and every concrete use site stores only the parameter bindings
+/// for that single definition.
+#[derive(Clone, Debug)]
+pub enum MetaType {
+	/// A concrete, monomorphic Rust type, e.g. `u8` or `bool`.
+	Concrete(ConcreteMetaType),
+	/// The generic definition of a type, e.g. `Option<T>`, registered once
+	/// regardless of how many concrete parameterizations are in use.
+	Generic(GenericMetaType),
+	/// A reference to a named type parameter of a parent generic
+	/// definition, e.g. `T` within the definition of `Option<T>`.
+	Parameter(ParameterMetaType),
+	/// A generic definition together with the concrete types bound to its
+	/// parameters at a particular use site, e.g. `Option<T>` with `T = u8`.
+	Parameterized(ParameterizedMetaType),
+}
+
+impl MetaType {
+	/// Creates a new meta type from the given compile-time type.
+	pub fn new<T>() -> Self
+	where
+		T: Metadata + 'static,
+	{
+		MetaType::Concrete(ConcreteMetaType {
+			any_type_id: AnyTypeId::of::<T>(),
+			ctor: T::type_id,
+		})
+	}
+
+	/// Creates a meta type describing the generic definition of the family
+	/// identified by `G`, e.g. `Option<_>`, over the given parameter names.
+	///
+	/// `G` is a marker type unique to the generic family; the same `G` must
+	/// be used with [`MetaType::parameter`] and [`MetaType::parameterized`]
+	/// for this definition's concrete use sites to resolve correctly.
+	///
+	/// # Note
+	///
+	/// The generic definition should be registered only once. Concrete use
+	/// sites of the family should be constructed with
+	/// [`MetaType::parameterized`] instead, so the registry stores the
+	/// shared definition a single time and keeps only the per-use-site
+	/// bindings.
+	pub fn generic<G>(name: &'static str, namespace: Namespace, params: &'static [&'static str]) -> Self
+	where
+		G: 'static,
+	{
+		MetaType::Generic(GenericMetaType {
+			any_type_id: AnyTypeId::of::<G>(),
+			name,
+			namespace,
+			params,
+		})
+	}
+
+	/// Creates a meta type referring to the parameter `name` of the generic
+	/// family `G`, to be used within that family's own use sites.
+	pub fn parameter<G, Param>(name: &'static str) -> Self
+	where
+		G: 'static,
+		Param: 'static,
+	{
+		MetaType::Parameter(ParameterMetaType {
+			parent: AnyTypeId::of::<G>(),
+			param: AnyTypeId::of::<Param>(),
+			name,
+		})
+	}
+
+	/// Binds concrete parameter types to the generic family `G` at a
+	/// particular use site, e.g. `G = Option<_>`, `params = [u8]`.
+	pub fn parameterized<G, P>(params: P) -> Self
+	where
+		G: 'static,
+		P: IntoIterator<Item = MetaType>,
+	{
+		MetaType::Parameterized(ParameterizedMetaType {
+			parent: AnyTypeId::of::<G>(),
+			params: params.into_iter().collect(),
+		})
+	}
+}
+
+impl IntoCompact for MetaType {
+	type Output = <crate::form::CompactForm as crate::form::Form>::TypeId;
+
+	/// Registers this meta type with the given registry.
+	///
+	/// A `Concrete` type registers its own type definition as usual. A
+	/// `Generic` definition is registered once, keyed on the `any::TypeId`
+	/// of its family marker, and is returned as-is on every later lookup. A
+	/// `Parameter` resolves to whatever concrete type the enclosing
+	/// `Parameterized` use site bound to that parameter name. A
+	/// `Parameterized` type registers its bindings and returns a reference
+	/// to that specific instantiation, reusing the shared generic
+	/// definition rather than duplicating it.
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		registry.register_type(&self)
+	}
+}
+
+/// A concrete, monomorphic Rust type.
+#[derive(Clone, Debug)]
+pub struct ConcreteMetaType {
+	any_type_id: AnyTypeId,
+	ctor: fn() -> TypeId,
+}
+
+impl ConcreteMetaType {
+	pub(crate) fn any_type_id(&self) -> AnyTypeId {
+		self.any_type_id
+	}
+
+	pub(crate) fn definition(&self) -> TypeId {
+		(self.ctor)()
+	}
+}
+
+/// The generic definition of a Rust type, over its named parameters.
+#[derive(Clone, Debug)]
+pub struct GenericMetaType {
+	any_type_id: AnyTypeId,
+	name: &'static str,
+	namespace: Namespace,
+	params: &'static [&'static str],
+}
+
+impl GenericMetaType {
+	pub(crate) fn any_type_id(&self) -> AnyTypeId {
+		self.any_type_id
+	}
+
+	pub(crate) fn name(&self) -> &'static str {
+		self.name
+	}
+
+	pub(crate) fn namespace(&self) -> &Namespace {
+		&self.namespace
+	}
+
+	pub(crate) fn params(&self) -> &'static [&'static str] {
+		self.params
+	}
+}
+
+/// A reference to a named parameter within a parent generic definition.
+#[derive(Clone, Debug)]
+pub struct ParameterMetaType {
+	parent: AnyTypeId,
+	param: AnyTypeId,
+	name: &'static str,
+}
+
+impl ParameterMetaType {
+	pub(crate) fn parent(&self) -> AnyTypeId {
+		self.parent
+	}
+
+	pub(crate) fn param(&self) -> AnyTypeId {
+		self.param
+	}
+
+	pub(crate) fn name(&self) -> &'static str {
+		self.name
+	}
+}
+
+/// A generic family together with the concrete types bound to its
+/// parameters at a particular use site.
+#[derive(Clone, Debug)]
+pub struct ParameterizedMetaType {
+	parent: AnyTypeId,
+	params: Vec<MetaType>,
+}
+
+impl ParameterizedMetaType {
+	pub(crate) fn parent(&self) -> AnyTypeId {
+		self.parent
+	}
+
+	pub(crate) fn params(&self) -> &[MetaType] {
+		&self.params
+	}
+}
+
+/// A small FNV-1a hasher, so that a stable `u64` key can be derived from a
+/// `core::any::TypeId` without depending on `std`'s `RandomState`.
+#[derive(Default)]
+struct StableHasher(u64);
+
+impl Hasher for StableHasher {
+	fn finish(&self) -> u64 {
+		if self.0 == 0 {
+			0xcbf29ce484222325
+		} else {
+			self.0
+		}
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		let mut hash = if self.0 == 0 { 0xcbf29ce484222325 } else { self.0 };
+		for &byte in bytes {
+			hash ^= u64::from(byte);
+			hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+		}
+		self.0 = hash;
+	}
+}
+
+/// Derives a stable `u64` key for `id`.
+///
+/// # Note
+///
+/// `core::any::TypeId` does not implement `PartialOrd`/`Ord`, and does not
+/// expose its internal representation on stable Rust. Hashing it into a
+/// `u64` gives `MetaType` (and the registry's lookup tables, which key on
+/// this too) a total order and a `BTreeMap`-friendly key without a
+/// per-comparison allocation.
+pub(crate) fn any_type_id_key(id: &AnyTypeId) -> u64 {
+	let mut hasher = StableHasher::default();
+	id.hash(&mut hasher);
+	hasher.finish()
+}
+
+macro_rules! impl_ord_via_any_type_id_key {
+	($ty:ty, $field:ident) => {
+		impl PartialEq for $ty {
+			fn eq(&self, other: &Self) -> bool {
+				self.$field == other.$field
+			}
+		}
+
+		impl Eq for $ty {}
+
+		impl PartialOrd for $ty {
+			fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+				Some(self.cmp(other))
+			}
+		}
+
+		impl Ord for $ty {
+			fn cmp(&self, other: &Self) -> Ordering {
+				any_type_id_key(&self.$field).cmp(&any_type_id_key(&other.$field))
+			}
+		}
+	};
+}
+
+impl_ord_via_any_type_id_key!(ConcreteMetaType, any_type_id);
+impl_ord_via_any_type_id_key!(GenericMetaType, any_type_id);
+
+impl PartialEq for ParameterizedMetaType {
+	fn eq(&self, other: &Self) -> bool {
+		self.parent == other.parent && self.params == other.params
+	}
+}
+
+impl Eq for ParameterizedMetaType {}
+
+impl PartialOrd for ParameterizedMetaType {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for ParameterizedMetaType {
+	fn cmp(&self, other: &Self) -> Ordering {
+		any_type_id_key(&self.parent)
+			.cmp(&any_type_id_key(&other.parent))
+			.then_with(|| self.params.cmp(&other.params))
+	}
+}
+
+impl PartialEq for ParameterMetaType {
+	fn eq(&self, other: &Self) -> bool {
+		self.parent == other.parent && self.param == other.param && self.name == other.name
+	}
+}
+
+impl Eq for ParameterMetaType {}
+
+impl PartialOrd for ParameterMetaType {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for ParameterMetaType {
+	fn cmp(&self, other: &Self) -> Ordering {
+		any_type_id_key(&self.parent)
+			.cmp(&any_type_id_key(&other.parent))
+			.then_with(|| self.name.cmp(other.name))
+			.then_with(|| any_type_id_key(&self.param).cmp(&any_type_id_key(&other.param)))
+	}
+}
+
+impl PartialEq for MetaType {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for MetaType {}
+
+impl PartialOrd for MetaType {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for MetaType {
+	fn cmp(&self, other: &Self) -> Ordering {
+		fn discriminant(mt: &MetaType) -> u8 {
+			match mt {
+				MetaType::Concrete(_) => 0,
+				MetaType::Generic(_) => 1,
+				MetaType::Parameter(_) => 2,
+				MetaType::Parameterized(_) => 3,
+			}
+		}
+		match (self, other) {
+			(MetaType::Concrete(a), MetaType::Concrete(b)) => a.cmp(b),
+			(MetaType::Generic(a), MetaType::Generic(b)) => a.cmp(b),
+			(MetaType::Parameter(a), MetaType::Parameter(b)) => a.cmp(b),
+			(MetaType::Parameterized(a), MetaType::Parameterized(b)) => a.cmp(b),
+			_ => discriminant(self).cmp(&discriminant(other)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct MarkerA;
+	struct MarkerB;
+
+	#[test]
+	fn any_type_id_key_is_stable_and_distinguishes_types() {
+		let a = any_type_id_key(&AnyTypeId::of::<MarkerA>());
+		let a_again = any_type_id_key(&AnyTypeId::of::<MarkerA>());
+		let b = any_type_id_key(&AnyTypeId::of::<MarkerB>());
+		assert_eq!(a, a_again);
+		assert_ne!(a, b);
+	}
+
+	#[test]
+	fn meta_type_ord_is_consistent_with_eq() {
+		let a = MetaType::parameter::<MarkerA, u8>("T");
+		let a_again = MetaType::parameter::<MarkerA, u8>("T");
+		let b = MetaType::parameter::<MarkerA, u8>("U");
+		assert_eq!(a.cmp(&a_again), Ordering::Equal);
+		assert!(a == a_again);
+		assert_ne!(a.cmp(&b), Ordering::Equal);
+	}
+}
@@ -0,0 +1,473 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tm_std::*;
+
+use core::any::TypeId as AnyTypeId;
+
+use crate::{
+	form::CompactForm,
+	meta_type::any_type_id_key,
+	type_id::{FromTablesError, Namespace, TypeId, TypeIdCustom, TypeSubstitutes},
+	MetaType,
+};
+
+/// Converts `self` into its compact form, registering any nested type or
+/// string data with the given `registry` along the way.
+pub trait IntoCompact {
+	/// The compacted output of this conversion.
+	type Output;
+
+	/// Compacts `self`, registering nested data with `registry`.
+	fn into_compact(self, registry: &mut Registry) -> Self::Output;
+}
+
+/// The single shared definition of a generic type family, e.g. `Option<T>`.
+#[derive(Debug)]
+struct GenericDef {
+	/// The index of this definition's own `TypeIdCustom` entry in `types`.
+	index: u32,
+	/// The registry-index name of the generic type.
+	name: u32,
+	/// The compacted namespace of the generic type.
+	namespace: Namespace<CompactForm>,
+	/// The declared names of the generic type's parameters, in order.
+	params: &'static [&'static str],
+}
+
+/// The parameter bindings in scope while compacting one use site of a
+/// generic family, so any [`MetaType::Parameter`] nested within its
+/// argument types can resolve to the concrete type bound to it.
+#[derive(Debug)]
+struct ParameterFrame {
+	parent: AnyTypeId,
+	bindings: Map<&'static str, u32>,
+}
+
+/// Accumulates the strings and type definitions referenced by a
+/// [`MetaType`] graph, de-duplicating them into flat, densely-indexed
+/// tables.
+///
+/// A `Registry` is what [`IntoCompact::into_compact`] registers nested data
+/// with; the resulting tables are what a compacted `TypeId<CompactForm>`
+/// graph's indices refer into.
+#[derive(Default, Debug)]
+pub struct Registry {
+	strings: Vec<&'static str>,
+	string_table: Map<&'static str, u32>,
+	types: Vec<TypeId<CompactForm>>,
+	/// Indexes `types` by a stable key derived from the `any::TypeId` of
+	/// the concrete Rust type that produced each entry, so repeated
+	/// registrations of the same type dedupe to a single entry.
+	type_table: Map<u64, u32>,
+	/// Indexes the single shared definition of each generic family, keyed
+	/// by a stable key derived from the family's marker `any::TypeId`.
+	generics: Map<u64, GenericDef>,
+	/// Indexes a generic family's parameterizations by family key together
+	/// with the registry indices bound to its parameters, so the same
+	/// instantiation (e.g. `Option<u8>`) dedupes to a single entry.
+	parameterized_table: Map<(u64, Vec<u32>), u32>,
+	/// The stack of parameter bindings currently in scope, innermost last.
+	parameter_stack: Vec<ParameterFrame>,
+	/// The `any::TypeId` that produced each concrete entry in `types`, so
+	/// that resolving a [`MetaType::Parameter`] can sanity-check the bound
+	/// type actually matches what the parameter was declared over.
+	concrete_type_ids: Map<u32, AnyTypeId>,
+}
+
+impl Registry {
+	/// Creates a new, empty registry.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers `string`, returning its index in the string table.
+	pub fn register_string(&mut self, string: &'static str) -> u32 {
+		if let Some(&index) = self.string_table.get(string) {
+			return index;
+		}
+		let index = self.strings.len() as u32;
+		self.strings.push(string);
+		self.string_table.insert(string, index);
+		index
+	}
+
+	/// Registers `meta`, returning the index of its compacted definition.
+	pub fn register_type(&mut self, meta: &MetaType) -> u32 {
+		match meta {
+			MetaType::Concrete(concrete) => self.register_concrete(concrete.any_type_id(), concrete.definition()),
+			MetaType::Generic(generic) => self.register_generic(
+				generic.any_type_id(),
+				generic.name(),
+				generic.namespace().clone(),
+				generic.params(),
+			),
+			MetaType::Parameter(parameter) => self.resolve_parameter(parameter.parent(), parameter.name(), parameter.param()),
+			MetaType::Parameterized(parameterized) => {
+				self.register_parameterized(parameterized.parent(), parameterized.params().to_vec())
+			}
+		}
+	}
+
+	/// Registers the definition of a concrete, monomorphic type.
+	fn register_concrete(&mut self, any_type_id: AnyTypeId, definition: TypeId) -> u32 {
+		let key = any_type_id_key(&any_type_id);
+		if let Some(&index) = self.type_table.get(&key) {
+			return index;
+		}
+		let compacted = definition.into_compact(self);
+		let index = self.types.len() as u32;
+		self.types.push(compacted);
+		self.type_table.insert(key, index);
+		self.concrete_type_ids.insert(index, any_type_id);
+		index
+	}
+
+	/// Registers the shared definition of a generic family, if it has not
+	/// already been registered, and returns its entry's index either way.
+	fn register_generic(
+		&mut self,
+		any_type_id: AnyTypeId,
+		name: &'static str,
+		namespace: Namespace,
+		params: &'static [&'static str],
+	) -> u32 {
+		let key = any_type_id_key(&any_type_id);
+		if let Some(def) = self.generics.get(&key) {
+			return def.index;
+		}
+		let name_index = self.register_string(name);
+		let namespace = namespace.into_compact(self);
+		let index = self.types.len() as u32;
+		self.types.push(TypeId::Custom(TypeIdCustom::from_compact_parts(
+			name_index,
+			namespace.clone(),
+			Vec::new(),
+			None,
+		)));
+		self.generics.insert(
+			key,
+			GenericDef {
+				index,
+				name: name_index,
+				namespace,
+				params,
+			},
+		);
+		index
+	}
+
+	/// Resolves the parameter `name` of the generic family `parent` to the
+	/// registry index bound to it by the nearest enclosing use site.
+	///
+	/// If the resolved index was registered from a concrete Rust type,
+	/// `expected` (the `any::TypeId` the parameter was declared over, via
+	/// [`crate::MetaType::parameter`]'s `Param` type argument) is checked
+	/// against it in debug builds, to catch a `MetaType` graph whose
+	/// parameter references and bindings have drifted apart.
+	///
+	/// # Panics
+	///
+	/// Panics if no enclosing [`MetaType::Parameterized`] use site of
+	/// `parent` is currently being registered, or it has no binding for
+	/// `name` — both indicate a `MetaType` graph that was built
+	/// inconsistently.
+	fn resolve_parameter(&mut self, parent: AnyTypeId, name: &'static str, expected: AnyTypeId) -> u32 {
+		let index = self
+			.parameter_stack
+			.iter()
+			.rev()
+			.find(|frame| frame.parent == parent)
+			.and_then(|frame| frame.bindings.get(name).copied())
+			.unwrap_or_else(|| panic!("no binding in scope for parameter `{}`", name));
+		if let Some(&bound_type) = self.concrete_type_ids.get(&index) {
+			debug_assert_eq!(
+				any_type_id_key(&bound_type),
+				any_type_id_key(&expected),
+				"parameter `{}` resolved to an unexpected concrete type",
+				name
+			);
+		}
+		index
+	}
+
+	/// Registers a use site of the generic family `parent`, bound to
+	/// `params`, reusing the family's single shared definition.
+	///
+	/// # Panics
+	///
+	/// Panics if `parent`'s generic definition has not been registered yet,
+	/// or `params` does not have exactly as many entries as the
+	/// definition's declared parameters.
+	fn register_parameterized(&mut self, parent: AnyTypeId, params: Vec<MetaType>) -> u32 {
+		let parent_key = any_type_id_key(&parent);
+		let param_indices: Vec<u32> = params.iter().map(|param| self.register_type(param)).collect();
+
+		let dedup_key = (parent_key, param_indices.clone());
+		if let Some(&index) = self.parameterized_table.get(&dedup_key) {
+			return index;
+		}
+
+		let (def_index, def_name, def_namespace, param_names) = {
+			let def = self
+				.generics
+				.get(&parent_key)
+				.unwrap_or_else(|| panic!("generic definition must be registered before its parameterizations"));
+			(def.index, def.name, def.namespace.clone(), def.params)
+		};
+		assert_eq!(
+			param_indices.len(),
+			param_names.len(),
+			"parameter count mismatch for generic definition `{}`",
+			def_name
+		);
+
+		let bindings: Map<&'static str, u32> = param_names.iter().copied().zip(param_indices.iter().copied()).collect();
+		self.parameter_stack.push(ParameterFrame { parent, bindings });
+
+		let index = self.types.len() as u32;
+		self.types.push(TypeId::Custom(TypeIdCustom::from_compact_parts(
+			def_name,
+			def_namespace,
+			param_indices.clone(),
+			Some(def_index),
+		)));
+		self.parameterized_table.insert(dedup_key, index);
+
+		self.parameter_stack.pop();
+		index
+	}
+
+	/// Rewrites every compacted custom type matched by `substitutes` so
+	/// that references to it point at its replacement type instead.
+	///
+	/// # Note
+	///
+	/// The matched entry's own slot in the type table is left in place
+	/// (nothing else may reference it once rewritten, but indices already
+	/// handed out elsewhere must stay valid) — only the *references* to it
+	/// are redirected.
+	pub fn substitute(&mut self, substitutes: &TypeSubstitutes) {
+		let mut remaps = Vec::new();
+		for index in 0..self.types.len() {
+			let custom = match &self.types[index] {
+				TypeId::Custom(custom) => custom,
+				_ => continue,
+			};
+			let namespace: Vec<&'static str> = custom.namespace().segments().iter().map(|&seg| self.strings[seg as usize]).collect();
+			let name = self.strings[*custom.name() as usize];
+			let arity = custom.type_params().len();
+			if let Some(replacement) = substitutes.resolve_resolved(&namespace, name, arity) {
+				let replacement = replacement.clone();
+				let replacement_index = self.register_type(&replacement);
+				if replacement_index != index as u32 {
+					remaps.push((index as u32, replacement_index));
+				}
+			}
+		}
+		for (from, to) in remaps {
+			for ty in &mut self.types {
+				ty.remap_refs(from, to);
+			}
+		}
+	}
+
+	/// Reconstructs a registry from previously compacted string and type
+	/// tables, e.g. after decoding them off the wire.
+	///
+	/// Returns an error if any index referenced by `types` is out of range
+	/// for either table, rather than silently accepting a corrupt graph.
+	pub fn from_tables(strings: Vec<&'static str>, types: Vec<TypeId<CompactForm>>) -> Result<Self, FromTablesError> {
+		let types_len = types.len() as u32;
+		let strings_len = strings.len() as u32;
+		for ty in &types {
+			ty.validate_refs(types_len, strings_len)?;
+		}
+		let string_table = strings.iter().enumerate().map(|(index, &s)| (s, index as u32)).collect();
+		Ok(Self {
+			strings,
+			string_table,
+			types,
+			type_table: Map::new(),
+			generics: Map::new(),
+			parameterized_table: Map::new(),
+			parameter_stack: Vec::new(),
+			concrete_type_ids: Map::new(),
+		})
+	}
+
+	/// The registered strings, indexed as referenced by [`Self::types`].
+	pub fn strings(&self) -> &[&'static str] {
+		&self.strings
+	}
+
+	/// The registered, compacted type definitions.
+	pub fn types(&self) -> &[TypeId<CompactForm>] {
+		&self.types
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{type_id::TypeMatcher, HasTypeId, TypeIdPrimitive, TypeIdTuple};
+
+	struct Bool;
+	impl HasTypeId for Bool {
+		fn type_id() -> TypeId {
+			TypeId::Primitive(TypeIdPrimitive::Bool)
+		}
+	}
+
+	struct U8;
+	impl HasTypeId for U8 {
+		fn type_id() -> TypeId {
+			TypeId::Primitive(TypeIdPrimitive::U8)
+		}
+	}
+
+	struct OptionMarker;
+
+	/// A tuple whose only element is the `my_crate::BoundedVec` generic definition.
+	struct TupleOfBoundedVec;
+	impl HasTypeId for TupleOfBoundedVec {
+		fn type_id() -> TypeId {
+			TypeId::Tuple(TypeIdTuple::new(vec![MetaType::generic::<OptionMarker>(
+				"BoundedVec",
+				Namespace::new(vec!["my_crate"]).unwrap(),
+				&["T"],
+			)]))
+		}
+	}
+
+	#[test]
+	fn register_string_dedupes() {
+		let mut registry = Registry::new();
+		let a = registry.register_string("hello");
+		let b = registry.register_string("world");
+		let c = registry.register_string("hello");
+		assert_eq!(a, c);
+		assert_ne!(a, b);
+	}
+
+	#[test]
+	fn register_concrete_dedupes() {
+		let mut registry = Registry::new();
+		let a = registry.register_type(&MetaType::new::<Bool>());
+		let b = registry.register_type(&MetaType::new::<Bool>());
+		assert_eq!(a, b);
+		assert_eq!(registry.types().len(), 1);
+	}
+
+	#[test]
+	fn generic_definition_is_registered_once_and_shared_across_use_sites() {
+		let mut registry = Registry::new();
+		let namespace = Namespace::new(vec!["core", "option"]).unwrap();
+
+		let option_u8 =
+			MetaType::parameterized::<OptionMarker, _>(vec![MetaType::generic::<OptionMarker>("Option", namespace.clone(), &["T"])]);
+		// Registering the generic definition directly, as a derive macro would
+		// before emitting any of its use sites.
+		let generic_index = registry.register_type(&MetaType::generic::<OptionMarker>("Option", namespace, &["T"]));
+
+		let use_site_u8 = registry.register_type(&MetaType::parameterized::<OptionMarker, _>(vec![MetaType::new::<U8>()]));
+		let use_site_u8_again = registry.register_type(&MetaType::parameterized::<OptionMarker, _>(vec![MetaType::new::<U8>()]));
+		let use_site_bool = registry.register_type(&MetaType::parameterized::<OptionMarker, _>(vec![MetaType::new::<Bool>()]));
+
+		assert_eq!(use_site_u8, use_site_u8_again, "identical instantiations dedupe");
+		assert_ne!(use_site_u8, use_site_bool, "different bindings are distinct entries");
+		assert_ne!(use_site_u8, generic_index, "a use site is not the shared definition itself");
+
+		let shared_definition = match &registry.types()[generic_index as usize] {
+			TypeId::Custom(custom) => custom,
+			_ => panic!("expected a custom type"),
+		};
+		assert!(shared_definition.generic().is_none(), "the definition itself has no parent");
+
+		let use_site = match &registry.types()[use_site_u8 as usize] {
+			TypeId::Custom(custom) => custom,
+			_ => panic!("expected a custom type"),
+		};
+		assert_eq!(use_site.generic(), Some(&generic_index), "a use site points back at the shared definition");
+
+		let _ = option_u8;
+	}
+
+	#[test]
+	#[should_panic(expected = "generic definition must be registered")]
+	fn register_parameterized_without_generic_definition_panics() {
+		let mut registry = Registry::new();
+		registry.register_type(&MetaType::parameterized::<OptionMarker, _>(vec![MetaType::new::<U8>()]));
+	}
+
+	#[test]
+	fn substitute_rewrites_references_to_matched_custom_type() {
+		let mut registry = Registry::new();
+		let namespace = Namespace::new(vec!["my_crate"]).unwrap();
+		let bounded_vec = MetaType::generic::<OptionMarker>("BoundedVec", namespace.clone(), &["T"]);
+		let bounded_vec_index = registry.register_type(&bounded_vec);
+		// Something else references the matched type, e.g. as a tuple element.
+		let tuple_index = registry.register_type(&MetaType::new::<TupleOfBoundedVec>());
+
+		let mut substitutes = TypeSubstitutes::new();
+		substitutes.insert(
+			TypeMatcher::new(namespace, "BoundedVec").with_arity(0),
+			MetaType::new::<U8>(),
+		);
+		registry.substitute(&substitutes);
+
+		let replacement_index = registry
+			.types()
+			.iter()
+			.position(|ty| matches!(ty, TypeId::Primitive(TypeIdPrimitive::U8)))
+			.expect("replacement type was registered") as u32;
+		assert_ne!(replacement_index, bounded_vec_index);
+		assert_eq!(
+			registry.types()[tuple_index as usize],
+			TypeId::Tuple(TypeIdTuple::<CompactForm> {
+				type_params: vec![replacement_index]
+			}),
+			"the tuple element now points at the replacement"
+		);
+	}
+
+	#[test]
+	fn from_tables_round_trips() {
+		let mut registry = Registry::new();
+		registry.register_type(&MetaType::new::<Bool>());
+		registry.register_type(&MetaType::new::<U8>());
+
+		let strings = registry.strings().to_vec();
+		let types = registry.types().to_vec();
+		let rebuilt = Registry::from_tables(strings, types).expect("valid tables round-trip");
+		assert_eq!(rebuilt.types(), registry.types());
+		assert_eq!(rebuilt.strings(), registry.strings());
+	}
+
+	#[test]
+	fn from_tables_rejects_dangling_type_index() {
+		let dangling = TypeId::Custom(TypeIdCustom::from_compact_parts(0, Namespace::<CompactForm>::from_segments(vec![]), vec![5], None));
+		let err = Registry::from_tables(vec!["x"], vec![dangling]).unwrap_err();
+		assert_eq!(err, FromTablesError::TypeIndexOutOfRange { index: 5 });
+	}
+
+	#[test]
+	fn from_tables_rejects_dangling_string_index() {
+		let dangling = TypeId::Custom(TypeIdCustom::from_compact_parts(5, Namespace::<CompactForm>::from_segments(vec![]), vec![], None));
+		let err = Registry::from_tables(vec!["x"], vec![dangling]).unwrap_err();
+		assert_eq!(err, FromTablesError::StringIndexOutOfRange { index: 5 });
+	}
+}
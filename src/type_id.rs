@@ -22,7 +22,8 @@ use crate::{
 	IntoCompact, MetaType, Metadata, Registry,
 };
 use derive_more::From;
-use serde::Serialize;
+use parity_scale_codec::{Compact, Decode, Encode, Error as CodecError, Input, Output};
+use serde::{Deserialize, Serialize};
 
 /// Implementors return their meta type identifiers.
 pub trait HasTypeId {
@@ -36,7 +37,8 @@ pub trait HasTypeId {
 /// The first segment represents the crate name in which the type has been defined.
 ///
 /// Rust prelude type may have an empty namespace definition.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(serialize = "F::String: Serialize", deserialize = "F::String: Deserialize<'de>"))]
 #[serde(transparent)]
 pub struct Namespace<F: Form = MetaForm> {
 	/// The segments of the namespace.
@@ -70,6 +72,28 @@ impl IntoCompact for Namespace {
 	}
 }
 
+/// SCALE-encodes a compacted namespace as its registry-index segments.
+impl Encode for Namespace<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		Compact(self.segments.len() as u32).encode_to(dest);
+		for segment in &self.segments {
+			Compact(*segment).encode_to(dest);
+		}
+	}
+}
+
+/// SCALE-decodes a compacted namespace from its registry-index segments.
+impl Decode for Namespace<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let len = Compact::<u32>::decode(input)?.0;
+		let mut segments = Vec::with_capacity(len as usize);
+		for _ in 0..len {
+			segments.push(Compact::<u32>::decode(input)?.0);
+		}
+		Ok(Namespace { segments })
+	}
+}
+
 impl Namespace {
 	/// Creates a new namespace from the given segments.
 	pub fn new<S>(segments: S) -> Result<Self, NamespaceError>
@@ -101,14 +125,29 @@ impl Namespace {
 	}
 }
 
+impl<F: Form> Namespace<F> {
+	/// Creates a namespace directly from already-compacted or already
+	/// validated segments, bypassing [`Namespace::new`]'s identifier checks.
+	#[cfg(test)]
+	pub(crate) fn from_segments(segments: Vec<F::String>) -> Self {
+		Self { segments }
+	}
+
+	/// The segments of this namespace.
+	pub(crate) fn segments(&self) -> &[F::String] {
+		&self.segments
+	}
+}
+
+
 /// A type identifier.
 ///
 /// This uniquely identifies types and can be used to refer to type definitions.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, From, Debug, Serialize)]
-#[serde(bound = "
-	F::TypeId: Serialize,
-	F::IndirectTypeId: Serialize
-")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, From, Debug, Serialize, Deserialize)]
+#[serde(bound(
+	serialize = "F::TypeId: Serialize, F::IndirectTypeId: Serialize, F::String: Serialize",
+	deserialize = "F::TypeId: Deserialize<'de>, F::IndirectTypeId: Deserialize<'de>, F::String: Deserialize<'de>"
+))]
 #[serde(untagged)]
 pub enum TypeId<F: Form = MetaForm> {
 	/// A custom type defined by the user.
@@ -121,6 +160,12 @@ pub enum TypeId<F: Form = MetaForm> {
 	Tuple(TypeIdTuple<F>),
 	/// A Rust primitive type.
 	Primitive(TypeIdPrimitive),
+	/// A shared or exclusive reference type, e.g. `&T` or `&mut T`.
+	Reference(TypeIdReference<F>),
+	/// A raw pointer type, e.g. `*const T` or `*mut T`.
+	Pointer(TypeIdPointer<F>),
+	/// A function pointer type, e.g. `fn(A) -> B`.
+	FnPtr(TypeIdFnPtr<F>),
 }
 
 impl IntoCompact for TypeId {
@@ -133,12 +178,72 @@ impl IntoCompact for TypeId {
 			TypeId::Array(array) => array.into_compact(registry).into(),
 			TypeId::Tuple(tuple) => tuple.into_compact(registry).into(),
 			TypeId::Primitive(primitive) => primitive.into(),
+			TypeId::Reference(reference) => reference.into_compact(registry).into(),
+			TypeId::Pointer(pointer) => pointer.into_compact(registry).into(),
+			TypeId::FnPtr(fn_ptr) => fn_ptr.into_compact(registry).into(),
+		}
+	}
+}
+
+/// SCALE-encodes a compacted type identifier as a single-byte variant
+/// discriminant followed by the variant's own encoding.
+impl Encode for TypeId<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		match self {
+			TypeId::Custom(custom) => {
+				dest.push_byte(0);
+				custom.encode_to(dest);
+			}
+			TypeId::Slice(slice) => {
+				dest.push_byte(1);
+				slice.encode_to(dest);
+			}
+			TypeId::Array(array) => {
+				dest.push_byte(2);
+				array.encode_to(dest);
+			}
+			TypeId::Tuple(tuple) => {
+				dest.push_byte(3);
+				tuple.encode_to(dest);
+			}
+			TypeId::Primitive(primitive) => {
+				dest.push_byte(4);
+				primitive.encode_to(dest);
+			}
+			TypeId::Reference(reference) => {
+				dest.push_byte(5);
+				reference.encode_to(dest);
+			}
+			TypeId::Pointer(pointer) => {
+				dest.push_byte(6);
+				pointer.encode_to(dest);
+			}
+			TypeId::FnPtr(fn_ptr) => {
+				dest.push_byte(7);
+				fn_ptr.encode_to(dest);
+			}
+		}
+	}
+}
+
+impl Decode for TypeId<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		match input.read_byte()? {
+			0 => Ok(TypeId::Custom(TypeIdCustom::decode(input)?)),
+			1 => Ok(TypeId::Slice(TypeIdSlice::decode(input)?)),
+			2 => Ok(TypeId::Array(TypeIdArray::decode(input)?)),
+			3 => Ok(TypeId::Tuple(TypeIdTuple::decode(input)?)),
+			4 => Ok(TypeId::Primitive(TypeIdPrimitive::decode(input)?)),
+			5 => Ok(TypeId::Reference(TypeIdReference::decode(input)?)),
+			6 => Ok(TypeId::Pointer(TypeIdPointer::decode(input)?)),
+			7 => Ok(TypeId::FnPtr(TypeIdFnPtr::decode(input)?)),
+			_ => Err(CodecError::from("TypeId: invalid variant discriminant")),
 		}
 	}
 }
 
 /// Identifies a primitive Rust type.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug, Encode, Decode)]
 #[serde(rename_all = "lowercase")]
 pub enum TypeIdPrimitive {
 	/// `bool` type
@@ -172,8 +277,11 @@ pub enum TypeIdPrimitive {
 }
 
 /// A type identifier for custom type definitions.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(
+	serialize = "F::TypeId: Serialize, F::String: Serialize",
+	deserialize = "F::TypeId: Deserialize<'de>, F::String: Deserialize<'de>"
+))]
 pub struct TypeIdCustom<F: Form = MetaForm> {
 	/// The name of the custom type.
 	#[serde(rename = "custom.name")]
@@ -186,8 +294,25 @@ pub struct TypeIdCustom<F: Form = MetaForm> {
 	#[serde(rename = "custom.namespace")]
 	namespace: Namespace<F>,
 	/// The generic type parameters of the custom type in use.
+	///
+	/// # Note
+	///
+	/// These are the concrete bindings for this particular use site, not a
+	/// copy of the generic definition's fields — `Option<u8>` and
+	/// `Option<u32>` share the single registered definition of `Option<T>`
+	/// and differ only in what they bind here. See [`crate::MetaType`].
 	#[serde(rename = "custom.params")]
 	type_params: Vec<F::TypeId>,
+	/// The shared generic definition this use site instantiates, if any.
+	///
+	/// # Note
+	///
+	/// Always `None` pre-compaction. `Registry::register_parameterized`
+	/// populates this when compacting a [`crate::MetaType::Parameterized`]
+	/// use site, pointing it at the single registered entry for its generic
+	/// family instead of duplicating that entry's definition.
+	#[serde(rename = "custom.generic")]
+	generic: Option<F::TypeId>,
 }
 
 impl IntoCompact for TypeIdCustom {
@@ -202,10 +327,54 @@ impl IntoCompact for TypeIdCustom {
 				.into_iter()
 				.map(|param| registry.register_type(&param))
 				.collect::<Vec<_>>(),
+			generic: None,
 		}
 	}
 }
 
+/// SCALE-encodes a compacted custom type identifier as its registry-index
+/// name, namespace, type parameters and, if any, generic definition.
+impl Encode for TypeIdCustom<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		Compact(self.name).encode_to(dest);
+		self.namespace.encode_to(dest);
+		Compact(self.type_params.len() as u32).encode_to(dest);
+		for param in &self.type_params {
+			Compact(*param).encode_to(dest);
+		}
+		match self.generic {
+			Some(generic) => {
+				dest.push_byte(1);
+				Compact(generic).encode_to(dest);
+			}
+			None => dest.push_byte(0),
+		}
+	}
+}
+
+impl Decode for TypeIdCustom<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let name = Compact::<u32>::decode(input)?.0;
+		let namespace = Namespace::<CompactForm>::decode(input)?;
+		let len = Compact::<u32>::decode(input)?.0;
+		let mut type_params = Vec::with_capacity(len as usize);
+		for _ in 0..len {
+			type_params.push(Compact::<u32>::decode(input)?.0);
+		}
+		let generic = match input.read_byte()? {
+			0 => None,
+			1 => Some(Compact::<u32>::decode(input)?.0),
+			_ => return Err(CodecError::from("TypeIdCustom: invalid generic tag")),
+		};
+		Ok(TypeIdCustom {
+			name,
+			namespace,
+			type_params,
+			generic,
+		})
+	}
+}
+
 impl TypeIdCustom {
 	/// Creates a new type identifier to refer to a custom type definition.
 	pub fn new<T>(name: &'static str, namespace: Namespace, type_params: T) -> Self
@@ -216,13 +385,58 @@ impl TypeIdCustom {
 			name,
 			namespace,
 			type_params: type_params.into_iter().collect(),
+			generic: None,
 		}
 	}
 }
 
+impl<F: Form> TypeIdCustom<F> {
+	/// Creates a custom type identifier directly from its compacted parts.
+	///
+	/// # Note
+	///
+	/// Used by `Registry::register_generic`/`register_parameterized`, which
+	/// bypass the usual `IntoCompact` flow to dedupe a generic family's
+	/// shared definition.
+	pub(crate) fn from_compact_parts(
+		name: F::String,
+		namespace: Namespace<F>,
+		type_params: Vec<F::TypeId>,
+		generic: Option<F::TypeId>,
+	) -> Self {
+		Self {
+			name,
+			namespace,
+			type_params,
+			generic,
+		}
+	}
+
+	/// The shared generic definition this use site instantiates, if any.
+	#[cfg(test)]
+	pub(crate) fn generic(&self) -> Option<&F::TypeId> {
+		self.generic.as_ref()
+	}
+
+	/// The name of this custom type.
+	pub(crate) fn name(&self) -> &F::String {
+		&self.name
+	}
+
+	/// The namespace this custom type was defined in.
+	pub(crate) fn namespace(&self) -> &Namespace<F> {
+		&self.namespace
+	}
+
+	/// The concrete type parameters bound at this use site.
+	pub(crate) fn type_params(&self) -> &[F::TypeId] {
+		&self.type_params
+	}
+}
+
 /// An array type identifier.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::IndirectTypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(serialize = "F::IndirectTypeId: Serialize", deserialize = "F::IndirectTypeId: Deserialize<'de>"))]
 pub struct TypeIdArray<F: Form = MetaForm> {
 	/// The length of the array type definition.
 	#[serde(rename = "array.len")]
@@ -243,6 +457,23 @@ impl IntoCompact for TypeIdArray {
 	}
 }
 
+/// SCALE-encodes a compacted array type identifier as its fixed-width
+/// length followed by the registry-index element type.
+impl Encode for TypeIdArray<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		self.len.encode_to(dest);
+		Compact(self.type_param).encode_to(dest);
+	}
+}
+
+impl Decode for TypeIdArray<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let len = u16::decode(input)?;
+		let type_param = Compact::<u32>::decode(input)?.0;
+		Ok(TypeIdArray { len, type_param })
+	}
+}
+
 impl TypeIdArray {
 	/// Creates a new identifier to refer to array type definition.
 	pub fn new(len: u16, type_param: MetaType) -> Self {
@@ -251,8 +482,8 @@ impl TypeIdArray {
 }
 
 /// A type identifier to refer to tuple types.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::TypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(serialize = "F::TypeId: Serialize", deserialize = "F::TypeId: Deserialize<'de>"))]
 #[serde(transparent)]
 pub struct TypeIdTuple<F: Form = MetaForm> {
 	/// The types in the tuple type definition.
@@ -273,6 +504,28 @@ impl IntoCompact for TypeIdTuple {
 	}
 }
 
+/// SCALE-encodes a compacted tuple type identifier as its registry-index
+/// element types.
+impl Encode for TypeIdTuple<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		Compact(self.type_params.len() as u32).encode_to(dest);
+		for param in &self.type_params {
+			Compact(*param).encode_to(dest);
+		}
+	}
+}
+
+impl Decode for TypeIdTuple<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let len = Compact::<u32>::decode(input)?.0;
+		let mut type_params = Vec::with_capacity(len as usize);
+		for _ in 0..len {
+			type_params.push(Compact::<u32>::decode(input)?.0);
+		}
+		Ok(TypeIdTuple { type_params })
+	}
+}
+
 impl TypeIdTuple {
 	/// Creates a new tuple type definition from the given types.
 	pub fn new<T>(type_params: T) -> Self
@@ -291,8 +544,8 @@ impl TypeIdTuple {
 }
 
 /// A type identifier to refer to slice type definitions.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Debug)]
-#[serde(bound = "F::IndirectTypeId: Serialize")]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(serialize = "F::IndirectTypeId: Serialize", deserialize = "F::IndirectTypeId: Deserialize<'de>"))]
 pub struct TypeIdSlice<F: Form = MetaForm> {
 	/// The element type of the slice type definition.
 	#[serde(rename = "slice.type")]
@@ -309,6 +562,21 @@ impl IntoCompact for TypeIdSlice {
 	}
 }
 
+/// SCALE-encodes a compacted slice type identifier as its registry-index
+/// element type.
+impl Encode for TypeIdSlice<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		Compact(self.type_param).encode_to(dest);
+	}
+}
+
+impl Decode for TypeIdSlice<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let type_param = Compact::<u32>::decode(input)?.0;
+		Ok(TypeIdSlice { type_param })
+	}
+}
+
 impl TypeIdSlice {
 	/// Creates a new type identifier to refer to slice type definitions.
 	///
@@ -328,6 +596,410 @@ impl TypeIdSlice {
 	}
 }
 
+/// A type identifier to refer to reference types, e.g. `&T` or `&mut T`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(serialize = "F::IndirectTypeId: Serialize", deserialize = "F::IndirectTypeId: Deserialize<'de>"))]
+pub struct TypeIdReference<F: Form = MetaForm> {
+	/// Is `true` if the reference is mutable.
+	#[serde(rename = "ref.mutable")]
+	pub mutable: bool,
+	/// The referenced type.
+	#[serde(rename = "ref.type")]
+	pub type_param: F::IndirectTypeId,
+}
+
+impl IntoCompact for TypeIdReference {
+	type Output = TypeIdReference<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		TypeIdReference {
+			mutable: self.mutable,
+			type_param: registry.register_type(&self.type_param),
+		}
+	}
+}
+
+/// SCALE-encodes a compacted reference type identifier as its mutability
+/// flag followed by the registry-index referenced type.
+impl Encode for TypeIdReference<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		self.mutable.encode_to(dest);
+		Compact(self.type_param).encode_to(dest);
+	}
+}
+
+impl Decode for TypeIdReference<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let mutable = bool::decode(input)?;
+		let type_param = Compact::<u32>::decode(input)?.0;
+		Ok(TypeIdReference { mutable, type_param })
+	}
+}
+
+impl TypeIdReference {
+	/// Creates a new type identifier to refer to a shared reference, `&T`.
+	pub fn new_shared(type_param: MetaType) -> Self {
+		Self {
+			mutable: false,
+			type_param,
+		}
+	}
+
+	/// Creates a new type identifier to refer to a mutable reference, `&mut T`.
+	pub fn new_mutable(type_param: MetaType) -> Self {
+		Self {
+			mutable: true,
+			type_param,
+		}
+	}
+}
+
+/// A type identifier to refer to raw pointer types, e.g. `*const T` or `*mut T`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(serialize = "F::IndirectTypeId: Serialize", deserialize = "F::IndirectTypeId: Deserialize<'de>"))]
+pub struct TypeIdPointer<F: Form = MetaForm> {
+	/// Is `true` if the pointer is mutable, i.e. `*mut T` rather than `*const T`.
+	#[serde(rename = "ptr.mutable")]
+	pub mutable: bool,
+	/// The pointee type.
+	#[serde(rename = "ptr.type")]
+	pub type_param: F::IndirectTypeId,
+}
+
+impl IntoCompact for TypeIdPointer {
+	type Output = TypeIdPointer<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		TypeIdPointer {
+			mutable: self.mutable,
+			type_param: registry.register_type(&self.type_param),
+		}
+	}
+}
+
+/// SCALE-encodes a compacted pointer type identifier as its mutability
+/// flag followed by the registry-index pointee type.
+impl Encode for TypeIdPointer<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		self.mutable.encode_to(dest);
+		Compact(self.type_param).encode_to(dest);
+	}
+}
+
+impl Decode for TypeIdPointer<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let mutable = bool::decode(input)?;
+		let type_param = Compact::<u32>::decode(input)?.0;
+		Ok(TypeIdPointer { mutable, type_param })
+	}
+}
+
+impl TypeIdPointer {
+	/// Creates a new type identifier to refer to a constant raw pointer, `*const T`.
+	pub fn new_const(type_param: MetaType) -> Self {
+		Self {
+			mutable: false,
+			type_param,
+		}
+	}
+
+	/// Creates a new type identifier to refer to a mutable raw pointer, `*mut T`.
+	pub fn new_mut(type_param: MetaType) -> Self {
+		Self {
+			mutable: true,
+			type_param,
+		}
+	}
+}
+
+/// A type identifier to refer to function pointer types, e.g. `fn(A) -> B`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+#[serde(bound(
+	serialize = "F::TypeId: Serialize, F::IndirectTypeId: Serialize",
+	deserialize = "F::TypeId: Deserialize<'de>, F::IndirectTypeId: Deserialize<'de>"
+))]
+pub struct TypeIdFnPtr<F: Form = MetaForm> {
+	/// The argument types of the function pointer.
+	#[serde(rename = "fnptr.inputs")]
+	pub inputs: Vec<F::TypeId>,
+	/// The return type of the function pointer.
+	#[serde(rename = "fnptr.output")]
+	pub output: F::IndirectTypeId,
+}
+
+impl IntoCompact for TypeIdFnPtr {
+	type Output = TypeIdFnPtr<CompactForm>;
+
+	fn into_compact(self, registry: &mut Registry) -> Self::Output {
+		TypeIdFnPtr {
+			inputs: self
+				.inputs
+				.into_iter()
+				.map(|input| registry.register_type(&input))
+				.collect::<Vec<_>>(),
+			output: registry.register_type(&self.output),
+		}
+	}
+}
+
+/// SCALE-encodes a compacted function pointer type identifier as its
+/// registry-index argument types followed by the registry-index return type.
+impl Encode for TypeIdFnPtr<CompactForm> {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		Compact(self.inputs.len() as u32).encode_to(dest);
+		for input in &self.inputs {
+			Compact(*input).encode_to(dest);
+		}
+		Compact(self.output).encode_to(dest);
+	}
+}
+
+impl Decode for TypeIdFnPtr<CompactForm> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let len = Compact::<u32>::decode(input)?.0;
+		let mut inputs = Vec::with_capacity(len as usize);
+		for _ in 0..len {
+			inputs.push(Compact::<u32>::decode(input)?.0);
+		}
+		let output = Compact::<u32>::decode(input)?.0;
+		Ok(TypeIdFnPtr { inputs, output })
+	}
+}
+
+impl TypeIdFnPtr {
+	/// Creates a new type identifier to refer to a function pointer type
+	/// from the given argument and return types.
+	pub fn new<I>(inputs: I, output: MetaType) -> Self
+	where
+		I: IntoIterator<Item = MetaType>,
+	{
+		Self {
+			inputs: inputs.into_iter().collect(),
+			output,
+		}
+	}
+}
+
+/// Matches a custom type definition by its namespace and name, and
+/// optionally the number of type parameters it is used with.
+///
+/// # Note
+///
+/// Used together with [`TypeSubstitutes`] to let a caller present a custom
+/// type, e.g. `my_crate::BoundedVec`, to downstream consumers as some other
+/// registered type or canonical shape instead.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TypeMatcher {
+	namespace: Namespace,
+	ident: &'static str,
+	arity: Option<usize>,
+}
+
+impl TypeMatcher {
+	/// Creates a new matcher for the custom type named `ident` within `namespace`.
+	pub fn new(namespace: Namespace, ident: &'static str) -> Self {
+		Self {
+			namespace,
+			ident,
+			arity: None,
+		}
+	}
+
+	/// Restricts this matcher to custom types used with exactly `arity` type parameters.
+	pub fn with_arity(mut self, arity: usize) -> Self {
+		self.arity = Some(arity);
+		self
+	}
+
+	/// Returns `true` if `custom` is identified by this matcher.
+	pub fn matches(&self, custom: &TypeIdCustom) -> bool {
+		custom.namespace.segments == self.namespace.segments
+			&& custom.name == self.ident
+			&& self.arity.is_none_or(|arity| custom.type_params.len() == arity)
+	}
+
+	/// Returns `true` if a compacted custom type definition named `name`
+	/// within `namespace` (both already resolved from a registry's string
+	/// table) and used with `arity` type parameters is identified by this
+	/// matcher.
+	///
+	/// # Note
+	///
+	/// This is the `CompactForm` counterpart to [`Self::matches`]: a
+	/// compacted registry only has `u32` string indices to work with, so
+	/// `Registry::substitute` resolves those indices back to their strings
+	/// before calling this method.
+	pub(crate) fn matches_resolved(&self, namespace: &[&'static str], name: &str, arity: usize) -> bool {
+		self.namespace.segments.iter().copied().eq(namespace.iter().copied())
+			&& self.ident == name
+			&& self.arity.is_none_or(|a| a == arity)
+	}
+}
+
+/// A set of substitution rules that replace matched custom type definitions
+/// with another registered type, e.g. an underlying canonical representation.
+///
+/// # Note
+///
+/// Applying these rules to a compacted `Registry` — rewriting every
+/// `F::TypeId`/`F::IndirectTypeId` reference that pointed at a matched
+/// custom type so it instead points at its replacement — is the
+/// responsibility of `Registry::substitute`, which lives outside this
+/// module alongside the rest of the registry's bookkeeping.
+#[derive(Default, Clone, Debug)]
+pub struct TypeSubstitutes {
+	substitutes: Vec<(TypeMatcher, MetaType)>,
+}
+
+impl TypeSubstitutes {
+	/// Creates an empty set of substitution rules.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers a rule that replaces any custom type matched by `matcher`
+	/// with `replacement`.
+	pub fn insert(&mut self, matcher: TypeMatcher, replacement: MetaType) {
+		self.substitutes.push((matcher, replacement));
+	}
+
+	/// Returns the replacement type for `custom`, if any rule matches it.
+	pub fn resolve(&self, custom: &TypeIdCustom) -> Option<&MetaType> {
+		self.substitutes
+			.iter()
+			.find(|(matcher, _)| matcher.matches(custom))
+			.map(|(_, replacement)| replacement)
+	}
+
+	/// Returns the replacement type for a compacted custom type definition
+	/// named `name` within `namespace` (both already resolved from a
+	/// registry's string table) and used with `arity` type parameters, if
+	/// any rule matches it.
+	pub(crate) fn resolve_resolved(&self, namespace: &[&'static str], name: &str, arity: usize) -> Option<&MetaType> {
+		self.substitutes
+			.iter()
+			.find(|(matcher, _)| matcher.matches_resolved(namespace, name, arity))
+			.map(|(_, replacement)| replacement)
+	}
+}
+
+/// An error returned by [`Registry::from_tables`](crate::Registry::from_tables)
+/// when a decoded type or string table contains a dangling reference.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FromTablesError {
+	/// A `TypeId<CompactForm>` referenced a type index beyond the bounds of
+	/// the decoded type table.
+	TypeIndexOutOfRange {
+		/// The out-of-range index.
+		index: u32,
+	},
+	/// A `TypeId<CompactForm>` referenced a string index beyond the bounds
+	/// of the decoded string table.
+	StringIndexOutOfRange {
+		/// The out-of-range index.
+		index: u32,
+	},
+}
+
+impl TypeId<CompactForm> {
+	/// Rewrites every registry-index reference equal to `from` into `to`.
+	///
+	/// Used by `Registry::substitute` to redirect everything that pointed
+	/// at a matched custom type onto its replacement.
+	pub(crate) fn remap_refs(&mut self, from: u32, to: u32) {
+		fn remap(idx: &mut u32, from: u32, to: u32) {
+			if *idx == from {
+				*idx = to;
+			}
+		}
+		match self {
+			TypeId::Custom(custom) => {
+				for param in &mut custom.type_params {
+					remap(param, from, to);
+				}
+				if let Some(generic) = &mut custom.generic {
+					remap(generic, from, to);
+				}
+			}
+			TypeId::Slice(slice) => remap(&mut slice.type_param, from, to),
+			TypeId::Array(array) => remap(&mut array.type_param, from, to),
+			TypeId::Tuple(tuple) => {
+				for param in &mut tuple.type_params {
+					remap(param, from, to);
+				}
+			}
+			TypeId::Primitive(_) => {}
+			TypeId::Reference(reference) => remap(&mut reference.type_param, from, to),
+			TypeId::Pointer(pointer) => remap(&mut pointer.type_param, from, to),
+			TypeId::FnPtr(fn_ptr) => {
+				for input in &mut fn_ptr.inputs {
+					remap(input, from, to);
+				}
+				remap(&mut fn_ptr.output, from, to);
+			}
+		}
+	}
+
+	/// Checks that every index `self` references is in range for a type
+	/// table of length `types_len` and a string table of length `strings_len`.
+	pub(crate) fn validate_refs(&self, types_len: u32, strings_len: u32) -> Result<(), FromTablesError> {
+		fn check_type(idx: u32, types_len: u32) -> Result<(), FromTablesError> {
+			if idx >= types_len {
+				Err(FromTablesError::TypeIndexOutOfRange { index: idx })
+			} else {
+				Ok(())
+			}
+		}
+		fn check_string(idx: u32, strings_len: u32) -> Result<(), FromTablesError> {
+			if idx >= strings_len {
+				Err(FromTablesError::StringIndexOutOfRange { index: idx })
+			} else {
+				Ok(())
+			}
+		}
+		match self {
+			TypeId::Custom(custom) => {
+				check_string(custom.name, strings_len)?;
+				for segment in &custom.namespace.segments {
+					check_string(*segment, strings_len)?;
+				}
+				for param in &custom.type_params {
+					check_type(*param, types_len)?;
+				}
+				if let Some(generic) = custom.generic {
+					check_type(generic, types_len)?;
+				}
+				Ok(())
+			}
+			TypeId::Slice(slice) => check_type(slice.type_param, types_len),
+			TypeId::Array(array) => check_type(array.type_param, types_len),
+			TypeId::Tuple(tuple) => {
+				for param in &tuple.type_params {
+					check_type(*param, types_len)?;
+				}
+				Ok(())
+			}
+			TypeId::Primitive(_) => Ok(()),
+			TypeId::Reference(reference) => check_type(reference.type_param, types_len),
+			TypeId::Pointer(pointer) => check_type(pointer.type_param, types_len),
+			TypeId::FnPtr(fn_ptr) => {
+				for input in &fn_ptr.inputs {
+					check_type(*input, types_len)?;
+				}
+				check_type(fn_ptr.output, types_len)
+			}
+		}
+	}
+}
+
+// The `CompactForm` instantiations of the types above round-trip through
+// both serde and SCALE: `Deserialize` is derived with the same serde
+// attributes as `Serialize`, so it reads back what `Serialize` writes.
+// `Registry::from_tables` rebuilds a registry from decoded string and type
+// tables and rejects any out-of-range index; it keeps the entries in their
+// `TypeId<CompactForm>` form and does not resolve them back into
+// `TypeId<MetaForm>`-shaped definitions.
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -379,4 +1051,93 @@ mod tests {
 			Err(NamespaceError::InvalidIdentifier { segment: 0 })
 		);
 	}
+
+	fn compact_custom(name: u32, namespace_segments: Vec<u32>, type_params: Vec<u32>, generic: Option<u32>) -> TypeId<CompactForm> {
+		TypeId::Custom(TypeIdCustom::from_compact_parts(
+			name,
+			Namespace { segments: namespace_segments },
+			type_params,
+			generic,
+		))
+	}
+
+	#[test]
+	fn type_id_compact_encode_decode_round_trip() {
+		let cases = vec![
+			compact_custom(0, vec![1, 2], vec![3, 4], Some(5)),
+			compact_custom(0, vec![], vec![], None),
+			TypeId::Slice(TypeIdSlice { type_param: 7u32 }),
+			TypeId::Array(TypeIdArray { len: 4, type_param: 9u32 }),
+			TypeId::Tuple(TypeIdTuple { type_params: vec![1, 2, 3] }),
+			TypeId::Primitive(TypeIdPrimitive::U128),
+			TypeId::Reference(TypeIdReference {
+				mutable: true,
+				type_param: 2u32,
+			}),
+			TypeId::Pointer(TypeIdPointer {
+				mutable: false,
+				type_param: 6u32,
+			}),
+			TypeId::FnPtr(TypeIdFnPtr {
+				inputs: vec![1, 2],
+				output: 3u32,
+			}),
+		];
+		for case in cases {
+			let encoded = case.encode();
+			let decoded = TypeId::<CompactForm>::decode(&mut &encoded[..]).expect("decodes");
+			assert_eq!(decoded, case);
+		}
+	}
+
+	#[test]
+	fn type_id_compact_decode_invalid_discriminant_errs() {
+		let encoded = [42u8];
+		assert!(TypeId::<CompactForm>::decode(&mut &encoded[..]).is_err());
+	}
+
+	#[test]
+	fn type_id_remap_refs_rewrites_matching_indices() {
+		let mut custom = compact_custom(0, vec![], vec![3, 4, 3], Some(3));
+		custom.remap_refs(3, 9);
+		assert_eq!(custom, compact_custom(0, vec![], vec![9, 4, 9], Some(9)));
+	}
+
+	#[test]
+	fn type_matcher_matches_resolved() {
+		let matcher = TypeMatcher::new(Namespace::new(vec!["my_crate"]).unwrap(), "BoundedVec").with_arity(1);
+		assert!(matcher.matches_resolved(&["my_crate"], "BoundedVec", 1));
+		assert!(!matcher.matches_resolved(&["my_crate"], "BoundedVec", 2));
+		assert!(!matcher.matches_resolved(&["other_crate"], "BoundedVec", 1));
+		assert!(!matcher.matches_resolved(&["my_crate"], "Other", 1));
+	}
+
+	#[test]
+	fn type_id_custom_compact_serde_round_trip() {
+		let custom = compact_custom(0, vec![1, 2], vec![3, 4], Some(5));
+		let json = serde_json::to_string(&custom).expect("serializes");
+		let decoded: TypeId<CompactForm> = serde_json::from_str(&json).expect("deserializes");
+		assert_eq!(decoded, custom);
+	}
+
+	#[test]
+	fn type_id_validate_refs_out_of_range_type_index() {
+		let custom = compact_custom(0, vec![], vec![7], None);
+		assert_eq!(
+			custom.validate_refs(1, 1),
+			Err(FromTablesError::TypeIndexOutOfRange { index: 7 })
+		);
+	}
+
+	#[test]
+	fn type_id_validate_refs_out_of_range_string_index() {
+		let custom = compact_custom(9, vec![], vec![], None);
+		assert_eq!(custom.validate_refs(1, 1), Err(FromTablesError::StringIndexOutOfRange { index: 9 }));
+	}
+
+	#[test]
+	fn type_id_validate_refs_ok() {
+		let custom = compact_custom(0, vec![0], vec![0], Some(0));
+		assert_eq!(custom.validate_refs(1, 1), Ok(()));
+	}
 }
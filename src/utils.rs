@@ -0,0 +1,45 @@
+// Copyright 2019
+//     by  Centrality Investments Ltd.
+//     and Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Returns `true` if the given string is a valid Rust identifier.
+pub fn is_rust_identifier(s: &str) -> bool {
+	let mut chars = s.chars();
+	match chars.next() {
+		Some(c) if c == '_' || c.is_alphabetic() => {}
+		_ => return false,
+	}
+	chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rust_identifier_ok() {
+		assert!(is_rust_identifier("hello"));
+		assert!(is_rust_identifier("_hello"));
+		assert!(is_rust_identifier("_"));
+		assert!(is_rust_identifier("Hello42"));
+	}
+
+	#[test]
+	fn rust_identifier_err() {
+		assert!(!is_rust_identifier(""));
+		assert!(!is_rust_identifier("1hello"));
+		assert!(!is_rust_identifier("hello world"));
+	}
+}